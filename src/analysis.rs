@@ -0,0 +1,81 @@
+//! Provides helpers to audit ciphertext for structural weaknesses.
+
+use std::collections::HashSet;
+
+/// Counts how many `block_size`-sized blocks of `ciphertext` repeat at least one earlier block.
+///
+/// Under ECB, identical plaintext blocks always produce identical ciphertext blocks, so a
+/// non-zero result is a strong signal that ECB (or an equally structure-leaking mode/round
+/// function combination) was used. CBC and CTR chain or combine blocks with per-block state, so
+/// repeated plaintext blocks do not produce repeated ciphertext blocks and this function should
+/// return 0 for them.
+///
+/// Any trailing partial block (shorter than `block_size`) is ignored. Inputs shorter than one
+/// block return 0.
+///
+/// # Panics
+///
+/// The specified block size was 0.
+///
+/// # Examples
+///
+/// ```
+/// use feistel::analysis::detect_ecb;
+///
+/// let ciphertext = [1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 7, 8];
+/// assert_eq!(1, detect_ecb(&ciphertext, 4));
+/// ```
+pub fn detect_ecb(ciphertext: &[u8], block_size: usize) -> usize {
+    assert!(block_size > 0, "Block size was 0!");
+
+    let mut seen = HashSet::new();
+    let mut duplicates = 0;
+
+    for block in ciphertext.chunks(block_size) {
+        if block.len() < block_size {
+            break;
+        }
+
+        if !seen.insert(block) {
+            duplicates += 1;
+        }
+    }
+
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_repeated_blocks() {
+        let ciphertext = [1, 2, 3, 4, 5, 6, 7, 8, 1, 2, 3, 4];
+        assert_eq!(1, detect_ecb(&ciphertext, 4));
+    }
+
+    #[test]
+    fn reports_zero_for_distinct_blocks() {
+        let ciphertext = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(0, detect_ecb(&ciphertext, 4));
+    }
+
+    #[test]
+    fn ignores_trailing_partial_block() {
+        let ciphertext = [1, 2, 3, 4, 1, 2, 3, 4, 1, 2];
+        assert_eq!(1, detect_ecb(&ciphertext, 4));
+    }
+
+    #[test]
+    fn reports_zero_for_input_shorter_than_one_block() {
+        let ciphertext = [1, 2, 3];
+        assert_eq!(0, detect_ecb(&ciphertext, 4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_zero_block_size() {
+        let ciphertext = [1, 2, 3, 4];
+        detect_ecb(&ciphertext, 0);
+    }
+}
@@ -1,10 +1,16 @@
 //! Provides functions to start applying specialized Feistel ciphers right away.
 
+pub mod analysis;
+pub mod mode;
 pub mod padding;
+pub mod stream;
 
-use padding::PaddingError;
+use {
+    mode::Mode,
+    padding::{Padding, PaddingError},
+};
 
-fn execute_rounds<K, F>(
+pub(crate) fn execute_rounds<K, F>(
     result: &mut [u8],
     block_size: usize,
     mut key_generator: K,
@@ -15,10 +21,13 @@ where
     K: FnMut() -> Vec<u8>,
     F: Fn(&[u8], &[u8]) -> Vec<u8>,
 {
-    let (mut start, mut middle, mut end): (usize, usize, usize);
-    let (mut left, mut right);
-    let mut key;
     let half_block_size = block_size/2;
+    // Reused across every round of every block instead of allocating fresh halves each time.
+    let mut scratch = vec![0u8; half_block_size];
+    let mut key;
+    let mut start;
+    let mut middle;
+    let mut end;
 
     start = 0;
     while start < result.len() {
@@ -26,29 +35,187 @@ where
         end = middle + half_block_size;
 
         for _ in 1..=rounds {
-            left = result[start..middle].to_owned();
-            right = result[middle..end].to_owned();
+            // Swaps the halves in place: the left half becomes the old right half.
+            for i in 0..half_block_size {
+                result.swap(start + i, middle + i);
+            }
 
-            result[start..middle].copy_from_slice(&right[..]);
-            
             // Produces the next right side
             key = key_generator();
-            right = round_function(&right[..], &key[..]);
+            scratch.copy_from_slice(&round_function(&result[start..middle], &key[..])[..]);
             for i in 0..half_block_size {
-                left[i] ^= right[i];
+                result[middle + i] ^= scratch[i];
             }
-            result[middle..end].copy_from_slice(&left[..]);
         }
 
-        left = result[start..middle].to_owned();
-        right = result[middle..end].to_owned();
-        result[start..middle].copy_from_slice(&right[..]);
-        result[middle..end].copy_from_slice(&left[..]);
-        
+        for i in 0..half_block_size {
+            result.swap(start + i, middle + i);
+        }
+
         start = end;
     }
 }
 
+fn xor_in_place(target: &mut [u8], other: &[u8]) {
+    for (t, o) in target.iter_mut().zip(other) {
+        *t ^= o;
+    }
+}
+
+fn increment_counter(counter: &mut [u8]) {
+    for byte in counter.iter_mut().rev() {
+        *byte = byte.wrapping_add(1);
+        if *byte != 0 {
+            break;
+        }
+    }
+}
+
+/// Encrypts a single CBC block in place: XORs it with `previous`, runs the Feistel rounds, then
+/// updates `previous` to the resulting ciphertext block. Shared by the one-shot and streaming
+/// encryption paths so a fix to CBC chaining only has to be made once.
+pub(crate) fn cbc_encrypt_step<K, F>(
+    block: &mut [u8],
+    previous: &mut Vec<u8>,
+    block_size: usize,
+    key_generator: &mut K,
+    round_function: &F,
+    rounds: usize,
+)
+where
+    K: FnMut() -> Vec<u8>,
+    F: Fn(&[u8], &[u8]) -> Vec<u8>,
+{
+    xor_in_place(block, &previous[..]);
+    execute_rounds(block, block_size, &mut *key_generator, &*round_function, rounds);
+    previous.copy_from_slice(block);
+}
+
+/// Deciphers a single CBC block in place: runs the Feistel rounds, then XORs the result with
+/// `previous`, before updating `previous` to the original ciphertext block. Shared by the
+/// one-shot and streaming decryption paths so a fix to CBC chaining only has to be made once.
+pub(crate) fn cbc_decrypt_step<K, F>(
+    block: &mut [u8],
+    previous: &mut Vec<u8>,
+    block_size: usize,
+    key_generator: &mut K,
+    round_function: &F,
+    rounds: usize,
+)
+where
+    K: FnMut() -> Vec<u8>,
+    F: Fn(&[u8], &[u8]) -> Vec<u8>,
+{
+    let cipher_block = block.to_owned();
+    execute_rounds(block, block_size, &mut *key_generator, &*round_function, rounds);
+    xor_in_place(block, &previous[..]);
+    *previous = cipher_block;
+}
+
+/// Enciphers `counter` into a keystream and XORs it against `data` in place, then advances the
+/// counter. `data` may be shorter than `block_size` for a trailing partial block. Since CTR
+/// encryption and decryption are the same operation, this is shared by both, and by the
+/// one-shot and streaming paths, so a fix to CTR chaining only has to be made once.
+pub(crate) fn ctr_step<K, F>(
+    counter: &mut Vec<u8>,
+    block_size: usize,
+    key_generator: &mut K,
+    round_function: &F,
+    rounds: usize,
+    data: &mut [u8],
+)
+where
+    K: FnMut() -> Vec<u8>,
+    F: Fn(&[u8], &[u8]) -> Vec<u8>,
+{
+    let mut keystream = counter.clone();
+    execute_rounds(&mut keystream[..], block_size, &mut *key_generator, &*round_function, rounds);
+    xor_in_place(data, &keystream[..data.len()]);
+    increment_counter(counter);
+}
+
+fn cipher_transform<K, F>(
+    buf: &mut [u8],
+    block_size: usize,
+    mode: Mode,
+    mut key_generator: K,
+    round_function: F,
+    rounds: usize,
+)
+where
+    K: FnMut() -> Vec<u8>,
+    F: Fn(&[u8], &[u8]) -> Vec<u8>,
+{
+    match mode {
+        Mode::Ecb => execute_rounds(buf, block_size, key_generator, round_function, rounds),
+        Mode::Cbc { iv } => {
+            assert!(iv.len() == block_size, "IV length must be equal to the block size!");
+
+            let mut previous = iv.to_owned();
+            let mut start = 0;
+            while start < buf.len() {
+                let end = start + block_size;
+                cbc_encrypt_step(&mut buf[start..end], &mut previous, block_size, &mut key_generator, &round_function, rounds);
+
+                start = end;
+            }
+        },
+        Mode::Ctr { nonce } => {
+            assert!(nonce.len() == block_size, "Nonce length must be equal to the block size!");
+
+            let mut counter = nonce.to_owned();
+            let mut start = 0;
+            while start < buf.len() {
+                let end = (start + block_size).min(buf.len());
+                ctr_step(&mut counter, block_size, &mut key_generator, &round_function, rounds, &mut buf[start..end]);
+
+                start = end;
+            }
+        },
+    }
+}
+
+fn decipher_transform<K, F>(
+    buf: &mut [u8],
+    block_size: usize,
+    mode: Mode,
+    mut key_generator: K,
+    round_function: F,
+    rounds: usize,
+)
+where
+    K: FnMut() -> Vec<u8>,
+    F: Fn(&[u8], &[u8]) -> Vec<u8>,
+{
+    match mode {
+        Mode::Ecb => execute_rounds(buf, block_size, key_generator, round_function, rounds),
+        Mode::Cbc { iv } => {
+            assert!(iv.len() == block_size, "IV length must be equal to the block size!");
+
+            let mut previous = iv.to_owned();
+            let mut start = 0;
+            while start < buf.len() {
+                let end = start + block_size;
+                cbc_decrypt_step(&mut buf[start..end], &mut previous, block_size, &mut key_generator, &round_function, rounds);
+
+                start = end;
+            }
+        },
+        Mode::Ctr { nonce } => {
+            assert!(nonce.len() == block_size, "Nonce length must be equal to the block size!");
+
+            let mut counter = nonce.to_owned();
+            let mut start = 0;
+            while start < buf.len() {
+                let end = (start + block_size).min(buf.len());
+                ctr_step(&mut counter, block_size, &mut key_generator, &round_function, rounds, &mut buf[start..end]);
+
+                start = end;
+            }
+        },
+    }
+}
+
 /// Returns an encrypted message.
 ///
 /// # Arguments
@@ -57,41 +224,82 @@ where
 ///
 /// * `block_size` - The data block size in bytes. It must be a multiple of 2.
 ///
-/// * `padder` - A closure that adds the necessary padding to the original message.
+/// * `mode` - The `Mode` of operation used to chain the blocks. `Mode::Ctr` ignores the chosen
+/// padding scheme, since it needs none.
 ///
 /// * `key_generator` - A FnMut closure that provides the key for each round.
 ///
 /// * `round_function` - A closure that receives a slice of a data block and a slice of a key to
 /// produce an owned output of the same size as the data block.
-/// 
+///
 /// * `rounds` - The number of times that the Fiestel cipher should be applied.
 ///
+/// The padding scheme is selected through the type parameter `P`, e.g.
+/// `cipher::<Pkcs7, _, _>(message, 16, Mode::Ecb, key_generator, round_function, rounds)`.
+///
 /// # Panics
 ///
-/// The specified block size was 0 or it was not a multiple of 2.
+/// The specified block size was 0, it was not a multiple of 2, or `mode` carried an IV/nonce
+/// whose length was not exactly `block_size`.
 pub fn cipher<P, K, F>(
     message: &[u8],
     block_size: usize,
-    padder: P,
+    mode: Mode,
     key_generator: K,
     round_function: F,
     rounds: usize,
 ) -> Vec<u8>
 where
-    P: Fn(&[u8], usize) -> Vec<u8>,
+    P: Padding,
     K: FnMut() -> Vec<u8>,
     F: Fn(&[u8], &[u8]) -> Vec<u8>,
 {
     assert!(block_size > 0, "Block size was 0!");
     assert!(block_size%2 == 0, "Block size was not a multiple of 2!");
 
-
-    let mut result = padder(message, block_size);
-    execute_rounds(&mut result[..], block_size, key_generator, round_function, rounds);
+    let mut result = if matches!(mode, Mode::Ctr { .. }) {
+        message.to_owned()
+    } else {
+        P::add(message, block_size)
+    };
+    cipher_transform(&mut result[..], block_size, mode, key_generator, round_function, rounds);
 
     result
 }
 
+/// Encrypts `buf` in place, without allocating a new buffer.
+///
+/// Unlike [`cipher`], this does not apply any padding: `buf` must already hold a block-aligned
+/// message (its length a multiple of `block_size`), except under `Mode::Ctr`, which tolerates a
+/// trailing partial block. Useful when the caller already owns a padded, block-aligned buffer
+/// and wants to avoid the copy that `cipher` makes internally.
+///
+/// # Panics
+///
+/// The specified block size was 0, it was not a multiple of 2, `mode` carried an IV/nonce whose
+/// length was not exactly `block_size`, or `buf`'s length was not a multiple of `block_size`
+/// under `Mode::Ecb`/`Mode::Cbc`.
+pub fn cipher_in_place<K, F>(
+    buf: &mut [u8],
+    block_size: usize,
+    mode: Mode,
+    key_generator: K,
+    round_function: F,
+    rounds: usize,
+)
+where
+    K: FnMut() -> Vec<u8>,
+    F: Fn(&[u8], &[u8]) -> Vec<u8>,
+{
+    assert!(block_size > 0, "Block size was 0!");
+    assert!(block_size%2 == 0, "Block size was not a multiple of 2!");
+    if !matches!(mode, Mode::Ctr { .. }) {
+        assert!(buf.len()%block_size == 0, "Buffer length must be a multiple of the block size!");
+    }
+
+    cipher_transform(buf, block_size, mode, key_generator, round_function, rounds);
+}
+
 /// Returns a desencrypted message.
 ///
 /// # Arguments
@@ -100,54 +308,94 @@ where
 ///
 /// * `block_size` - The data block size in bytes. It must be a multiple of 2.
 ///
+/// * `mode` - The `Mode` of operation used to chain the blocks. Must match the one used to
+/// produce `message`. `Mode::Ctr` ignores the chosen padding scheme, since it needs none.
+///
 /// * `key_generator` - A FnMut closure that provides the key for each round.
 ///
 /// * `round_function` - A closure that receives a slice of a data block and a slice of a key to
 /// produce an owned output of the same size as the data block.
-/// 
+///
 /// * `rounds` - The number of times that the Fiestel cipher should be applied.
 ///
-/// * `padding_remover` - A closure thar receives a desencrypted message stored in a Vec and
-/// removes its padding (which was neccesary during the encryption of the message). It produces a
-/// PaddingError in case that the message is malformed for the particular padding strategy.
+/// * `padding` - The `Padding` scheme that was used to pad the original message, e.g.
+/// `Pkcs7`. Its `remove` method is used to strip the padding back off once deciphered. Unused
+/// when `mode` is `Mode::Ctr`.
 ///
 /// # Panics
 ///
-/// The specified block size was 0 or it was not a multiple of 2.
+/// The specified block size was 0, it was not a multiple of 2, or `mode` carried an IV/nonce
+/// whose length was not exactly `block_size`.
 ///
 /// # Failures
 ///
-/// If the desencrypted messsage was not correctly padded according to the closure
-/// `padding_remover`, a `PaddingError` is produced.
-pub fn decipher<K, F, R>(
+/// If the desencrypted messsage was not correctly padded according to `padding`, a
+/// `PaddingError` is produced.
+pub fn decipher<K, F, P>(
     message: &[u8],
     block_size: usize,
+    mode: Mode,
     key_generator: K,
     round_function: F,
     rounds: usize,
-    padding_remover: R,
+    padding: P,
 ) -> Result<Vec<u8>, PaddingError>
 where
     K: FnMut() -> Vec<u8>,
     F: Fn(&[u8], &[u8]) -> Vec<u8>,
-    R: Fn(&mut Vec<u8>) -> Result<(), PaddingError>,
+    P: Padding,
 {
     assert!(block_size > 0, "Block size was 0!");
     assert!(block_size%2 == 0, "Block size was not a multiple of 2!");
 
-    let mut result = Vec::with_capacity(message.len());
-    result.extend_from_slice(message);
-    execute_rounds(&mut result[..], block_size, key_generator, round_function, rounds);
-    padding_remover(&mut result)?;
+    let needs_padding_removal = !matches!(mode, Mode::Ctr { .. });
+    let mut result = message.to_owned();
+    decipher_transform(&mut result[..], block_size, mode, key_generator, round_function, rounds);
+    if needs_padding_removal {
+        padding.remove(&mut result)?;
+    }
 
     Ok(result)
 }
 
+/// Deciphers `buf` in place, without allocating a new buffer.
+///
+/// Unlike [`decipher`], this does not remove any padding, since a `&mut [u8]` cannot be
+/// shrunk: it is up to the caller to strip padding from `buf` afterwards if needed. Useful when
+/// the caller already owns a block-aligned buffer and wants to avoid the copy that `decipher`
+/// makes internally.
+///
+/// # Panics
+///
+/// The specified block size was 0, it was not a multiple of 2, `mode` carried an IV/nonce whose
+/// length was not exactly `block_size`, or `buf`'s length was not a multiple of `block_size`
+/// under `Mode::Ecb`/`Mode::Cbc`.
+pub fn decipher_in_place<K, F>(
+    buf: &mut [u8],
+    block_size: usize,
+    mode: Mode,
+    key_generator: K,
+    round_function: F,
+    rounds: usize,
+)
+where
+    K: FnMut() -> Vec<u8>,
+    F: Fn(&[u8], &[u8]) -> Vec<u8>,
+{
+    assert!(block_size > 0, "Block size was 0!");
+    assert!(block_size%2 == 0, "Block size was not a multiple of 2!");
+    if !matches!(mode, Mode::Ctr { .. }) {
+        assert!(buf.len()%block_size == 0, "Buffer length must be a multiple of the block size!");
+    }
+
+    decipher_transform(buf, block_size, mode, key_generator, round_function, rounds);
+}
+
 #[cfg(test)]
 mod tests {
     use {
         super::*,
-        padding::pkcs7,
+        padding::pkcs7::Pkcs7,
     };
 
     fn slices_or(s1: &[u8], s2: &[u8]) -> Vec<u8> {
@@ -168,7 +416,7 @@ mod tests {
     }
 
     #[test]
-    fn test() {
+    fn test_ecb() {
         let message = b"Hello, World!";
         let key = b"Password";
         let mut key_count: u8 = 0;
@@ -180,13 +428,13 @@ mod tests {
                     result.push(key[i] ^ key_count);
                 }
                 key_count += 1;
-                
+
                 result
             };
-            cipher(
+            cipher::<Pkcs7, _, _>(
                 &message[..],
                 16,
-                pkcs7::add_padding,
+                Mode::Ecb,
                 keys_to_cipher,
                 slices_or,
                 50,
@@ -206,13 +454,162 @@ mod tests {
             decipher(
                 &ciphered[..],
                 16,
+                Mode::Ecb,
                 keys_to_decipher,
                 slices_or,
                 50,
-                pkcs7::remove_padding
+                Pkcs7,
+            ).unwrap()
+        };
+
+        assert_eq!(&message[..], &deciphered[..]);
+    }
+
+    #[test]
+    fn test_cbc() {
+        // The same 50-key schedule is reused for every block, as a real round function would.
+        let message = b"Identical block!Identical block!";
+        let key = b"Password";
+        let iv = [0x42u8; 16];
+        let rounds: u8 = 50;
+        let mut round: u8 = 0;
+
+        let ciphered = {
+            let keys_to_cipher = || {
+                let mut result = Vec::with_capacity(key.len());
+                for i in 0..key.len() {
+                    result.push(key[i] ^ round);
+                }
+                round = (round + 1) % rounds;
+
+                result
+            };
+            cipher::<Pkcs7, _, _>(
+                &message[..],
+                16,
+                Mode::Cbc { iv: &iv[..] },
+                keys_to_cipher,
+                slices_or,
+                rounds as usize,
+            )
+        };
+
+        let deciphered = {
+            let keys_to_decipher = || {
+                round = (round + rounds - 1) % rounds;
+                let mut result = Vec::with_capacity(key.len());
+                for i in 0..key.len() {
+                    result.push(key[i] ^ round);
+                }
+
+                result
+            };
+            decipher(
+                &ciphered[..],
+                16,
+                Mode::Cbc { iv: &iv[..] },
+                keys_to_decipher,
+                slices_or,
+                rounds as usize,
+                Pkcs7,
             ).unwrap()
         };
 
         assert_eq!(&message[..], &deciphered[..]);
+        // CBC hides repeated plaintext blocks, unlike ECB.
+        assert_ne!(&ciphered[0..16], &ciphered[16..32]);
+    }
+
+    #[test]
+    fn test_ctr() {
+        let message = b"Hello, World! This spans blocks and is not block-aligned!";
+        let key = b"Password";
+        let nonce = [0x07u8; 16];
+
+        let keys = || {
+            let mut result = Vec::with_capacity(key.len());
+            for i in 0..key.len() {
+                result.push(key[i]);
+            }
+
+            result
+        };
+        let ciphered = cipher::<Pkcs7, _, _>(
+            &message[..],
+            16,
+            Mode::Ctr { nonce: &nonce[..] },
+            keys,
+            slices_or,
+            50,
+        );
+
+        assert_eq!(message.len(), ciphered.len());
+
+        let deciphered = decipher(
+            &ciphered[..],
+            16,
+            Mode::Ctr { nonce: &nonce[..] },
+            keys,
+            slices_or,
+            50,
+            Pkcs7,
+        ).unwrap();
+
+        assert_eq!(&message[..], &deciphered[..]);
+    }
+
+    #[test]
+    fn test_in_place() {
+        let key = b"Password";
+        let mut key_count: u8 = 0;
+
+        let mut buf = Pkcs7::add(b"Hello, World!", 16);
+        {
+            let keys_to_cipher = || {
+                let mut result = Vec::with_capacity(key.len());
+                for i in 0..key.len() {
+                    result.push(key[i] ^ key_count);
+                }
+                key_count += 1;
+
+                result
+            };
+            cipher_in_place(&mut buf[..], 16, Mode::Ecb, keys_to_cipher, slices_or, 50);
+        }
+
+        {
+            let keys_to_decipher = || {
+                let mut result = Vec::with_capacity(key.len());
+                key_count -= 1;
+                for i in 0..key.len() {
+                    result.push(key[i] ^ key_count);
+                }
+
+                result
+            };
+            decipher_in_place(&mut buf[..], 16, Mode::Ecb, keys_to_decipher, slices_or, 50);
+        }
+        Pkcs7.remove(&mut buf).unwrap();
+
+        assert_eq!(b"Hello, World!", &buf[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cipher_in_place_rejects_misaligned_buffer_under_cbc() {
+        let iv = [0u8; 16];
+        cipher_in_place(&mut [0u8; 20], 16, Mode::Cbc { iv: &iv[..] }, || b"Password".to_vec(), slices_or, 50);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_decipher_in_place_rejects_misaligned_buffer_under_ecb() {
+        decipher_in_place(&mut [0u8; 20], 16, Mode::Ecb, || b"Password".to_vec(), slices_or, 50);
+    }
+
+    #[test]
+    fn test_cipher_in_place_allows_partial_trailing_block_under_ctr() {
+        let nonce = [0u8; 16];
+        cipher_in_place(&mut [0u8; 20], 16, Mode::Ctr { nonce: &nonce[..] }, || b"Password".to_vec(), slices_or, 50);
     }
 }
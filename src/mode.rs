@@ -0,0 +1,31 @@
+//! Provides the block cipher modes of operation supported by [`cipher`](crate::cipher) and
+//! [`decipher`](crate::decipher).
+
+/// The mode of operation used to chain the processing of individual blocks.
+#[derive(Debug, Clone, Copy)]
+pub enum Mode<'a> {
+    /// Electronic Codebook. Every block is processed independently, so identical plaintext
+    /// blocks produce identical ciphertext blocks.
+    Ecb,
+
+    /// Cipher Block Chaining. Before a block is enciphered it is XORed with the previous
+    /// ciphertext block (or `iv` for the first block); on the way back the block is deciphered
+    /// first and XORed with the previous ciphertext block afterwards.
+    Cbc {
+        /// The initialization vector. Must be `block_size` bytes long.
+        iv: &'a [u8],
+    },
+
+    /// Counter mode. Turns the cipher into a keystream generator: successive counter blocks,
+    /// starting at `nonce` and incremented by one per block, are enciphered and the result is
+    /// XORed against the message. Encryption and decryption are therefore the same operation,
+    /// and no padding is required, so the final partial block (if any) is handled transparently.
+    ///
+    /// Since the keystream is always produced by enciphering the counter, `cipher` and
+    /// `decipher` must be given the same `key_generator` sequence (the one used for encryption)
+    /// when using this mode.
+    Ctr {
+        /// The initial counter value. Must be `block_size` bytes long.
+        nonce: &'a [u8],
+    },
+}
@@ -0,0 +1,139 @@
+//! ANSI X.923 padding.
+
+use {
+    std::iter,
+    super::{Padding, PaddingError},
+};
+
+/// Pads with zero bytes, with the last byte holding the number of padding bytes added.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnsiX923;
+
+impl Padding for AnsiX923 {
+    /// Produces a padded message to fit the given block size following ANSI X.923.
+    ///
+    /// # Panics
+    ///
+    /// Only block sizes up to 256 are allowed. In any other case the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use feistel::padding::{Padding, ansi_x923::AnsiX923};
+    ///
+    /// let msg = b"Hello, World!";
+    /// let mut expected_result = Vec::with_capacity(msg.len() + 2);
+    /// expected_result.extend_from_slice(&msg[..]);
+    /// expected_result.push(0);
+    /// expected_result.push(2);
+    ///
+    /// let padded_msg = AnsiX923::add(&msg[..], 15);
+    ///
+    /// assert_eq!(&expected_result[..], &padded_msg[..]);
+    /// ```
+    fn add(message: &[u8], block_size: usize) -> Vec<u8> {
+        assert!(block_size <= 256, "Only block sizes up to 256 are allowed!");
+
+        let needed_padding = block_size - message.len() % block_size;
+        let mut result = Vec::with_capacity(message.len() + needed_padding);
+        result.extend_from_slice(message);
+        result.extend(iter::repeat(0u8).take(needed_padding - 1));
+        result.push(needed_padding as u8);
+
+        result
+    }
+
+    /// Deletes padding from a message following ANSI X.923.
+    ///
+    /// Validation runs in constant time with respect to the padding bytes: every failure,
+    /// whatever the reason, is reported through the same `PaddingError` without any early
+    /// return based on the padding's contents. This matters because `remove` is used to
+    /// validate decrypted CBC blocks, where a distinguishable "bad padding" error would open a
+    /// padding-oracle side channel.
+    ///
+    /// # Failures
+    /// If a valid padding is not found, a `PaddingError` is produced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use feistel::padding::{Padding, ansi_x923::AnsiX923};
+    ///
+    /// let msg = b"Hello, World!";
+    /// let mut msg_to_clean = Vec::with_capacity(msg.len() + 2);
+    /// msg_to_clean.extend_from_slice(&msg[..]);
+    /// msg_to_clean.push(0);
+    /// msg_to_clean.push(2);
+    ///
+    /// AnsiX923.remove(&mut msg_to_clean).unwrap();
+    ///
+    /// assert_eq!(&msg[..], &msg_to_clean[..]);
+    /// ```
+    fn remove(&self, message: &mut Vec<u8>) -> Result<(), PaddingError> {
+        // The largest padding value `add` can ever produce, per its own block size limit.
+        const MAX_PADDING: usize = 256;
+
+        let len = message.len();
+        if len == 0 {
+            return Err(PaddingError::new("Malformed padding."));
+        }
+
+        let padding_len = message[len - 1] as usize;
+        let mut bad = (padding_len == 0) as u8;
+        bad |= (padding_len > len) as u8;
+
+        // Always scan the same fixed-size trailing window, regardless of the claimed padding
+        // length, and accumulate mismatches with branchless boolean math instead of returning
+        // as soon as one is found. The count byte itself (offset 1) is excluded, since it is
+        // not required to be zero.
+        let window = MAX_PADDING.min(len);
+        for offset in 2..=window {
+            let is_padding_byte = (offset <= padding_len) as u8;
+            let mismatch = (message[len - offset] != 0x00) as u8;
+            bad |= is_padding_byte & mismatch;
+        }
+
+        if bad != 0 {
+            return Err(PaddingError::new("Malformed padding."));
+        }
+
+        message.truncate(len - padding_len);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_rejects_empty_message() {
+        let mut message = Vec::new();
+        assert!(AnsiX923.remove(&mut message).is_err());
+    }
+
+    #[test]
+    fn remove_rejects_zero_padding() {
+        let mut message = vec![1, 2, 3, 0];
+        assert!(AnsiX923.remove(&mut message).is_err());
+    }
+
+    #[test]
+    fn invalid_paddings_of_the_same_length_report_the_same_error() {
+        // Same claimed padding length (4), mismatch at a different offset each time: the code
+        // path taken must not depend on where the mismatch is, only on its length.
+        let variants = vec![
+            vec![1, 2, 3, 4, 0xff, 0, 0, 4],
+            vec![1, 2, 3, 4, 0, 0xff, 0, 4],
+            vec![1, 2, 3, 4, 0, 0, 0xff, 4],
+        ];
+
+        let messages: Vec<String> = variants
+            .into_iter()
+            .map(|mut message| AnsiX923.remove(&mut message).unwrap_err().to_string())
+            .collect();
+
+        assert!(messages.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+}
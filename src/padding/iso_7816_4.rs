@@ -0,0 +1,158 @@
+//! ISO/IEC 7816-4 padding.
+
+use {
+    std::iter,
+    super::{Padding, PaddingError},
+};
+
+/// Pads with a single `0x80` marker byte followed by `0x00` bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Iso7816_4;
+
+impl Padding for Iso7816_4 {
+    /// Produces a padded message to fit the given block size following ISO/IEC 7816-4.
+    ///
+    /// # Panics
+    ///
+    /// Only block sizes up to 256 are allowed. In any other case the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use feistel::padding::{Padding, iso_7816_4::Iso7816_4};
+    ///
+    /// let msg = b"Hello, World!";
+    /// let mut expected_result = Vec::with_capacity(msg.len() + 2);
+    /// expected_result.extend_from_slice(&msg[..]);
+    /// expected_result.push(0x80);
+    /// expected_result.push(0x00);
+    ///
+    /// let padded_msg = Iso7816_4::add(&msg[..], 15);
+    ///
+    /// assert_eq!(&expected_result[..], &padded_msg[..]);
+    /// ```
+    fn add(message: &[u8], block_size: usize) -> Vec<u8> {
+        assert!(block_size <= 256, "Only block sizes up to 256 are allowed!");
+
+        let needed_padding = block_size - message.len() % block_size;
+        let mut result = Vec::with_capacity(message.len() + needed_padding);
+        result.extend_from_slice(message);
+        result.push(0x80);
+        result.extend(iter::repeat(0x00u8).take(needed_padding - 1));
+
+        result
+    }
+
+    /// Deletes padding from a message following ISO/IEC 7816-4.
+    ///
+    /// Scans back over a fixed-size trailing window looking for the `0x80` marker.
+    ///
+    /// Validation runs in constant time with respect to the padding bytes: every failure,
+    /// whatever the reason, is reported through the same `PaddingError` without any early
+    /// return based on the padding's contents. This matters because `remove` is used to
+    /// validate decrypted CBC blocks, where a distinguishable "bad padding" error would open a
+    /// padding-oracle side channel.
+    ///
+    /// # Failures
+    /// If a valid padding is not found, a `PaddingError` is produced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use feistel::padding::{Padding, iso_7816_4::Iso7816_4};
+    ///
+    /// let msg = b"Hello, World!";
+    /// let mut msg_to_clean = Vec::with_capacity(msg.len() + 2);
+    /// msg_to_clean.extend_from_slice(&msg[..]);
+    /// msg_to_clean.push(0x80);
+    /// msg_to_clean.push(0x00);
+    ///
+    /// Iso7816_4.remove(&mut msg_to_clean).unwrap();
+    ///
+    /// assert_eq!(&msg[..], &msg_to_clean[..]);
+    /// ```
+    fn remove(&self, message: &mut Vec<u8>) -> Result<(), PaddingError> {
+        // The largest padding value `add` can ever produce, per its own block size limit.
+        const MAX_PADDING: usize = 256;
+
+        let len = message.len();
+        if len == 0 {
+            return Err(PaddingError::new("Malformed padding."));
+        }
+
+        // Scan a fixed-size trailing window looking for the `0x80` marker: `all_zero_so_far`
+        // tracks whether every byte scanned before the current offset was `0x00`, and
+        // `marker_found`/`padding_len` latch onto the first offset where that run of zeros is
+        // broken by a `0x80` byte, without ever returning early.
+        let window = MAX_PADDING.min(len);
+        let mut all_zero_so_far: u8 = 1;
+        let mut marker_found: u8 = 0;
+        let mut padding_len: usize = 0;
+        for offset in 1..=window {
+            let byte = message[len - offset];
+            let is_marker = all_zero_so_far & (byte == 0x80) as u8 & (1 - marker_found);
+            padding_len += is_marker as usize * offset;
+            marker_found |= is_marker;
+            all_zero_so_far &= (byte == 0x00) as u8;
+        }
+
+        if marker_found == 0 {
+            return Err(PaddingError::new("Malformed padding."));
+        }
+
+        message.truncate(len - padding_len);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_rejects_empty_message() {
+        let mut message = Vec::new();
+        assert!(Iso7816_4.remove(&mut message).is_err());
+    }
+
+    #[test]
+    fn remove_rejects_missing_marker() {
+        let mut message = vec![1, 2, 3, 0, 0, 0];
+        assert!(Iso7816_4.remove(&mut message).is_err());
+    }
+
+    #[test]
+    fn invalid_paddings_with_no_marker_report_the_same_error() {
+        // No `0x80` marker anywhere in the window, with the byte that breaks the run of zeros
+        // at a different offset each time: the code path taken must not depend on where that
+        // byte is, only on the fact that no marker was ever found.
+        let variants = vec![
+            vec![0xff, 0, 0, 0, 0, 0, 0, 0],
+            vec![0, 0xff, 0, 0, 0, 0, 0, 0],
+            vec![0, 0, 0xff, 0, 0, 0, 0, 0],
+        ];
+
+        let messages: Vec<String> = variants
+            .into_iter()
+            .map(|mut message| Iso7816_4.remove(&mut message).unwrap_err().to_string())
+            .collect();
+
+        assert!(messages.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_rejects_block_sizes_over_256() {
+        Iso7816_4::add(&[0xab; 10], 300);
+    }
+
+    #[test]
+    fn round_trips_at_the_largest_allowed_block_size() {
+        let message = [0xab; 10];
+        let mut padded = Iso7816_4::add(&message[..], 256);
+        Iso7816_4.remove(&mut padded).unwrap();
+
+        assert_eq!(&message[..], &padded[..]);
+    }
+}
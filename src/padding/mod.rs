@@ -0,0 +1,41 @@
+//! Provides a `Padding` trait and several common padding scheme implementations.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+pub mod pkcs7;
+pub mod ansi_x923;
+pub mod iso_7816_4;
+pub mod zero;
+
+/// Represents an anomaly found when removing padding from a message.
+#[derive(Debug)]
+pub struct PaddingError(String);
+
+impl PaddingError {
+    pub(crate) fn new<S: Into<String>>(message: S) -> Self {
+        PaddingError(message.into())
+    }
+}
+
+impl Display for PaddingError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A scheme to pad a message up to a multiple of a block size, and later remove that padding.
+///
+/// Implementations are provided for PKCS#7 ([`pkcs7::Pkcs7`]), ANSI X.923
+/// ([`ansi_x923::AnsiX923`]), ISO/IEC 7816-4 ([`iso_7816_4::Iso7816_4`]) and zero padding
+/// ([`zero::ZeroPadding`]).
+pub trait Padding {
+    /// Produces a padded message to fit the given block size.
+    fn add(message: &[u8], block_size: usize) -> Vec<u8>;
+
+    /// Deletes the padding previously added to a message.
+    ///
+    /// # Failures
+    ///
+    /// If a valid padding is not found, a `PaddingError` is produced.
+    fn remove(&self, message: &mut Vec<u8>) -> Result<(), PaddingError>;
+}
@@ -0,0 +1,86 @@
+//! Zero padding.
+
+use {
+    std::iter,
+    super::{Padding, PaddingError},
+};
+
+/// Pads with `0x00` bytes up to the next multiple of the block size.
+///
+/// This scheme is only losslessly reversible for messages that cannot legitimately end in a
+/// `0x00` byte, since [`remove`](Padding::remove) cannot otherwise distinguish padding from
+/// trailing data. In the degenerate case where the whole message is made of zero bytes it is
+/// impossible to tell an empty original message from an all-zero one, so that case is rejected
+/// as malformed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZeroPadding;
+
+impl Padding for ZeroPadding {
+    /// Produces a padded message to fit the given block size using zero padding.
+    ///
+    /// Unlike PKCS#7 or ANSI X.923, no padding is added when the message already is a multiple
+    /// of the block size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use feistel::padding::{Padding, zero::ZeroPadding};
+    ///
+    /// let msg = b"Hello, World!";
+    /// let mut expected_result = Vec::with_capacity(msg.len() + 2);
+    /// expected_result.extend_from_slice(&msg[..]);
+    /// expected_result.push(0);
+    /// expected_result.push(0);
+    ///
+    /// let padded_msg = ZeroPadding::add(&msg[..], 15);
+    ///
+    /// assert_eq!(&expected_result[..], &padded_msg[..]);
+    /// ```
+    fn add(message: &[u8], block_size: usize) -> Vec<u8> {
+        let remainder = message.len() % block_size;
+        let needed_padding = if remainder == 0 { 0 } else { block_size - remainder };
+        let mut result = Vec::with_capacity(message.len() + needed_padding);
+        result.extend_from_slice(message);
+        result.extend(iter::repeat(0x00u8).take(needed_padding));
+
+        result
+    }
+
+    /// Deletes padding from a message by trimming trailing `0x00` bytes.
+    ///
+    /// # Failures
+    /// If the message is empty, or entirely made of zero bytes, a `PaddingError` is produced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use feistel::padding::{Padding, zero::ZeroPadding};
+    ///
+    /// let msg = b"Hello, World!";
+    /// let mut msg_to_clean = Vec::with_capacity(msg.len() + 2);
+    /// msg_to_clean.extend_from_slice(&msg[..]);
+    /// msg_to_clean.push(0);
+    /// msg_to_clean.push(0);
+    ///
+    /// ZeroPadding.remove(&mut msg_to_clean).unwrap();
+    ///
+    /// assert_eq!(&msg[..], &msg_to_clean[..]);
+    /// ```
+    fn remove(&self, message: &mut Vec<u8>) -> Result<(), PaddingError> {
+        if message.is_empty() {
+            return Err(PaddingError::new("Empty message."));
+        }
+
+        while let Some(0x00) = message.last() {
+            message.pop();
+        }
+
+        if message.is_empty() {
+            return Err(PaddingError::new(
+                "Message is entirely zero bytes: cannot distinguish padding from content.",
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,377 @@
+//! Provides a stateful, incremental alternative to [`cipher`](crate::cipher) and
+//! [`decipher`](crate::decipher) for data that arrives in chunks.
+
+use std::marker::PhantomData;
+
+use crate::{
+    cbc_decrypt_step, cbc_encrypt_step, ctr_step, execute_rounds,
+    mode::Mode,
+    padding::{Padding, PaddingError},
+};
+
+/// Owned, per-chain state threaded across `update` calls, built once from the `Mode` given to
+/// `new` since a borrowed IV/nonce cannot outlive a single call.
+enum ChainState {
+    Ecb,
+    Cbc { previous: Vec<u8> },
+    Ctr { counter: Vec<u8> },
+}
+
+impl ChainState {
+    fn new(mode: Mode, block_size: usize) -> Self {
+        match mode {
+            Mode::Ecb => ChainState::Ecb,
+            Mode::Cbc { iv } => {
+                assert!(iv.len() == block_size, "IV length must be equal to the block size!");
+                ChainState::Cbc { previous: iv.to_owned() }
+            },
+            Mode::Ctr { nonce } => {
+                assert!(nonce.len() == block_size, "Nonce length must be equal to the block size!");
+                ChainState::Ctr { counter: nonce.to_owned() }
+            },
+        }
+    }
+}
+
+/// Incrementally encrypts a message as it arrives, chaining blocks according to the `Mode`
+/// given to [`new`](Encryptor::new).
+///
+/// Bytes are buffered until a full block is available; `update` then returns every complete
+/// block it can produce, and `finalize` pads and encrypts whatever partial block is left.
+/// `Mode::Ctr` needs no padding, so `finalize` simply encrypts whatever partial bytes remain.
+pub struct Encryptor<P, K, F> {
+    block_size: usize,
+    key_generator: K,
+    round_function: F,
+    rounds: usize,
+    chain: ChainState,
+    buffer: Vec<u8>,
+    _padding: PhantomData<P>,
+}
+
+impl<P, K, F> Encryptor<P, K, F>
+where
+    P: Padding,
+    K: FnMut() -> Vec<u8>,
+    F: Fn(&[u8], &[u8]) -> Vec<u8>,
+{
+    /// Creates a new `Encryptor`.
+    ///
+    /// # Panics
+    ///
+    /// The specified block size was 0, it was not a multiple of 2, or `mode` carried an IV/nonce
+    /// whose length was not exactly `block_size`.
+    pub fn new(block_size: usize, mode: Mode, key_generator: K, round_function: F, rounds: usize, _padding: P) -> Self {
+        assert!(block_size > 0, "Block size was 0!");
+        assert!(block_size%2 == 0, "Block size was not a multiple of 2!");
+
+        Encryptor {
+            block_size,
+            key_generator,
+            round_function,
+            rounds,
+            chain: ChainState::new(mode, block_size),
+            buffer: Vec::with_capacity(block_size),
+            _padding: PhantomData,
+        }
+    }
+
+    /// Buffers `data` and returns every complete, already encrypted block it allows for.
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(data);
+
+        let complete_len = self.buffer.len() - self.buffer.len()%self.block_size;
+        let mut result = self.buffer[..complete_len].to_owned();
+
+        match &mut self.chain {
+            ChainState::Ecb => {
+                execute_rounds(&mut result[..], self.block_size, &mut self.key_generator, &self.round_function, self.rounds);
+            },
+            ChainState::Cbc { previous } => {
+                let mut start = 0;
+                while start < result.len() {
+                    let end = start + self.block_size;
+                    cbc_encrypt_step(&mut result[start..end], previous, self.block_size, &mut self.key_generator, &self.round_function, self.rounds);
+
+                    start = end;
+                }
+            },
+            ChainState::Ctr { counter } => {
+                let mut start = 0;
+                while start < result.len() {
+                    let end = start + self.block_size;
+                    ctr_step(counter, self.block_size, &mut self.key_generator, &self.round_function, self.rounds, &mut result[start..end]);
+
+                    start = end;
+                }
+            },
+        }
+
+        self.buffer.drain(..complete_len);
+
+        result
+    }
+
+    /// Pads and encrypts the last, possibly partial, block. Under `Mode::Ctr` no padding is
+    /// applied: the remaining bytes, however few, are simply XORed with the keystream.
+    pub fn finalize(self) -> Vec<u8> {
+        let Encryptor { block_size, mut key_generator, round_function, rounds, chain, buffer, .. } = self;
+
+        match chain {
+            ChainState::Ecb => {
+                let mut result = P::add(&buffer[..], block_size);
+                execute_rounds(&mut result[..], block_size, key_generator, round_function, rounds);
+
+                result
+            },
+            ChainState::Cbc { mut previous } => {
+                let mut result = P::add(&buffer[..], block_size);
+                let mut start = 0;
+                while start < result.len() {
+                    let end = start + block_size;
+                    cbc_encrypt_step(&mut result[start..end], &mut previous, block_size, &mut key_generator, &round_function, rounds);
+
+                    start = end;
+                }
+
+                result
+            },
+            ChainState::Ctr { mut counter } => {
+                let mut result = buffer;
+                if !result.is_empty() {
+                    ctr_step(&mut counter, block_size, &mut key_generator, &round_function, rounds, &mut result[..]);
+                }
+
+                result
+            },
+        }
+    }
+}
+
+/// Incrementally deciphers a message as it arrives, chaining blocks according to the `Mode`
+/// given to [`new`](Decryptor::new).
+///
+/// Bytes are buffered until a full block is available. To let padding removal see the true
+/// final block, `update` always retains the last buffered block until `finalize` is called.
+/// `Mode::Ctr` needs no padding, so every full block is emitted as soon as it is available.
+pub struct Decryptor<P, K, F> {
+    block_size: usize,
+    key_generator: K,
+    round_function: F,
+    rounds: usize,
+    padding: P,
+    chain: ChainState,
+    buffer: Vec<u8>,
+}
+
+impl<P, K, F> Decryptor<P, K, F>
+where
+    P: Padding,
+    K: FnMut() -> Vec<u8>,
+    F: Fn(&[u8], &[u8]) -> Vec<u8>,
+{
+    /// Creates a new `Decryptor`.
+    ///
+    /// # Panics
+    ///
+    /// The specified block size was 0, it was not a multiple of 2, or `mode` carried an IV/nonce
+    /// whose length was not exactly `block_size`.
+    pub fn new(block_size: usize, mode: Mode, key_generator: K, round_function: F, rounds: usize, padding: P) -> Self {
+        assert!(block_size > 0, "Block size was 0!");
+        assert!(block_size%2 == 0, "Block size was not a multiple of 2!");
+
+        Decryptor {
+            block_size,
+            key_generator,
+            round_function,
+            rounds,
+            padding,
+            chain: ChainState::new(mode, block_size),
+            buffer: Vec::with_capacity(block_size),
+        }
+    }
+
+    /// Buffers `data` and returns every complete, already deciphered block it allows for.
+    /// Under `Mode::Ecb`/`Mode::Cbc` the last block is held back until `finalize`, since padding
+    /// removal needs to see it; `Mode::Ctr` needs no padding, so nothing is held back beyond an
+    /// incomplete trailing block.
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(data);
+
+        let complete_len = if matches!(self.chain, ChainState::Ctr { .. }) {
+            self.buffer.len() - self.buffer.len()%self.block_size
+        } else {
+            let available_to_emit = self.buffer.len().saturating_sub(self.block_size);
+            available_to_emit - available_to_emit%self.block_size
+        };
+        let mut result = self.buffer[..complete_len].to_owned();
+
+        match &mut self.chain {
+            ChainState::Ecb => {
+                execute_rounds(&mut result[..], self.block_size, &mut self.key_generator, &self.round_function, self.rounds);
+            },
+            ChainState::Cbc { previous } => {
+                let mut start = 0;
+                while start < result.len() {
+                    let end = start + self.block_size;
+                    cbc_decrypt_step(&mut result[start..end], previous, self.block_size, &mut self.key_generator, &self.round_function, self.rounds);
+
+                    start = end;
+                }
+            },
+            ChainState::Ctr { counter } => {
+                let mut start = 0;
+                while start < result.len() {
+                    let end = start + self.block_size;
+                    ctr_step(counter, self.block_size, &mut self.key_generator, &self.round_function, self.rounds, &mut result[start..end]);
+
+                    start = end;
+                }
+            },
+        }
+
+        self.buffer.drain(..complete_len);
+
+        result
+    }
+
+    /// Deciphers the last buffered block and strips its padding. Under `Mode::Ctr` no padding
+    /// is expected: whatever bytes remain (however few) are simply XORed with the keystream.
+    ///
+    /// # Failures
+    ///
+    /// If the final block was not correctly padded according to the `Padding` scheme given to
+    /// [`new`](Decryptor::new), a `PaddingError` is produced.
+    pub fn finalize(self) -> Result<Vec<u8>, PaddingError> {
+        let Decryptor { block_size, mut key_generator, round_function, rounds, padding, chain, buffer } = self;
+        let mut result = buffer;
+
+        match chain {
+            ChainState::Ecb => {
+                execute_rounds(&mut result[..], block_size, key_generator, round_function, rounds);
+                padding.remove(&mut result)?;
+            },
+            ChainState::Cbc { mut previous } => {
+                cbc_decrypt_step(&mut result[..], &mut previous, block_size, &mut key_generator, &round_function, rounds);
+                padding.remove(&mut result)?;
+            },
+            ChainState::Ctr { mut counter } => {
+                if !result.is_empty() {
+                    ctr_step(&mut counter, block_size, &mut key_generator, &round_function, rounds, &mut result[..]);
+                }
+            },
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::padding::pkcs7::Pkcs7,
+    };
+
+    fn slices_or(s1: &[u8], s2: &[u8]) -> Vec<u8> {
+        let (shortest, longest) = if s1.len() < s2.len() { (s1, s2) } else { (s2, s1) };
+        let mut result = Vec::with_capacity(longest.len());
+        let mut i: usize = 0;
+
+        while i < shortest.len() {
+            result.push(shortest[i] | longest[i]);
+            i += 1;
+        }
+        while i < longest.len() {
+            result.push(longest[i]);
+            i += 1;
+        }
+
+        result
+    }
+
+    #[test]
+    fn test_chunked_round_trip_ecb() {
+        let message = b"Hello, World! This message arrives in several small chunks.";
+        let key = b"Password";
+
+        let mut ciphered = Vec::new();
+        {
+            let mut encryptor = Encryptor::new(16, Mode::Ecb, || key.to_vec(), slices_or, 50, Pkcs7);
+            for chunk in message.chunks(5) {
+                ciphered.extend(encryptor.update(chunk));
+            }
+            ciphered.extend(encryptor.finalize());
+        }
+
+        let mut deciphered = Vec::new();
+        {
+            let mut decryptor = Decryptor::new(16, Mode::Ecb, || key.to_vec(), slices_or, 50, Pkcs7);
+            for chunk in ciphered.chunks(7) {
+                deciphered.extend(decryptor.update(chunk));
+            }
+            deciphered.extend(decryptor.finalize().unwrap());
+        }
+
+        assert_eq!(&message[..], &deciphered[..]);
+    }
+
+    #[test]
+    fn test_chunked_round_trip_cbc() {
+        let message = b"Identical block!Identical block!In several small chunks.";
+        let key = b"Password";
+        let iv = [0x42u8; 16];
+
+        let mut ciphered = Vec::new();
+        {
+            let mut encryptor = Encryptor::new(16, Mode::Cbc { iv: &iv[..] }, || key.to_vec(), slices_or, 50, Pkcs7);
+            for chunk in message.chunks(5) {
+                ciphered.extend(encryptor.update(chunk));
+            }
+            ciphered.extend(encryptor.finalize());
+        }
+
+        // CBC hides repeated plaintext blocks, unlike ECB.
+        assert_ne!(&ciphered[0..16], &ciphered[16..32]);
+
+        let mut deciphered = Vec::new();
+        {
+            let mut decryptor = Decryptor::new(16, Mode::Cbc { iv: &iv[..] }, || key.to_vec(), slices_or, 50, Pkcs7);
+            for chunk in ciphered.chunks(7) {
+                deciphered.extend(decryptor.update(chunk));
+            }
+            deciphered.extend(decryptor.finalize().unwrap());
+        }
+
+        assert_eq!(&message[..], &deciphered[..]);
+    }
+
+    #[test]
+    fn test_chunked_round_trip_ctr() {
+        let message = b"Hello, World! This spans blocks and is not block-aligned!";
+        let key = b"Password";
+        let nonce = [0x07u8; 16];
+
+        let mut ciphered = Vec::new();
+        {
+            let mut encryptor = Encryptor::new(16, Mode::Ctr { nonce: &nonce[..] }, || key.to_vec(), slices_or, 50, Pkcs7);
+            for chunk in message.chunks(5) {
+                ciphered.extend(encryptor.update(chunk));
+            }
+            ciphered.extend(encryptor.finalize());
+        }
+
+        assert_eq!(message.len(), ciphered.len());
+
+        let mut deciphered = Vec::new();
+        {
+            let mut decryptor = Decryptor::new(16, Mode::Ctr { nonce: &nonce[..] }, || key.to_vec(), slices_or, 50, Pkcs7);
+            for chunk in ciphered.chunks(7) {
+                deciphered.extend(decryptor.update(chunk));
+            }
+            deciphered.extend(decryptor.finalize().unwrap());
+        }
+
+        assert_eq!(&message[..], &deciphered[..]);
+    }
+}